@@ -4,28 +4,23 @@ use unicode_xid::UnicodeXID;
 /// excludes any line terminating white spaces.
 #[inline]
 pub fn is_white_space(c: char) -> bool {
-    match c {
-        '\u{9}' => true,
-        '\u{20}' => true,
-        '\u{A0}' => true,
-        '\u{1680}' => true,
-        '\u{2000}'..='\u{200A}' => true,
-        '\u{202f}' => true,
-        '\u{205f}' => true,
-        '\u{3000}' => true,
-        _ => false,
-    }
+    matches!(
+        c,
+        '\u{9}'
+            | '\u{20}'
+            | '\u{A0}'
+            | '\u{1680}'
+            | '\u{2000}'..='\u{200A}'
+            | '\u{202f}'
+            | '\u{205f}'
+            | '\u{3000}'
+    )
 }
 
 /// Returns whether or not the specified character is a Unicode line breaking white space character.
 #[inline]
 pub fn is_line_term(c: char) -> bool {
-    match c {
-        '\u{A}'..='\u{D}' => true,
-        '\u{85}' => true,
-        '\u{2028}'..='\u{2029}' => true,
-        _ => false
-    }
+    matches!(c, '\u{A}'..='\u{D}' | '\u{85}' | '\u{2028}'..='\u{2029}')
 }
 
 /// Returns whether or not the specified character is an identifier starting character.  Matches an
@@ -48,9 +43,21 @@ pub fn is_iden_continue(c: char) -> bool {
 /// any other punctuation character.
 #[inline]
 pub fn is_punctuator(c: char) -> bool {
-    match c {
+    matches!(
+        c,
         ';' | ',' | '.' | '(' | ')' | '{' | '}' | '[' | ']' | '@' | '#' | '~' | '?' | ':' | '$' | '='
-        | '!' | '<' | '>' | '-' | '&' | '|' | '+' | '*' | '/' | '^' | '%' => true,
-        _ => false
-    }
-}
\ No newline at end of file
+            | '!' | '<' | '>' | '-' | '&' | '|' | '+' | '*' | '/' | '^' | '%'
+    )
+}
+
+/// Returns whether or not the specified character may start a valid token.  Any other character starts
+/// an invalid token.
+#[inline]
+pub fn is_token_start(c: char) -> bool {
+    is_white_space(c)
+        || is_line_term(c)
+        || is_punctuator(c)
+        || is_iden_start(c)
+        || c == '"'
+        || c == '\''
+}
@@ -0,0 +1,117 @@
+use std::fmt;
+
+/// Identifiers which are reserved by the Flycatcher language.  Keywords are recognized at lex time, so
+/// the parser may match any keyword uniformly through [`Token::Keyword`](super::Token::Keyword).
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Keyword {
+    /// `as`, used for casting a value to another type.
+    As,
+
+    /// `break`, which exits the loop that it is used in.
+    Break,
+
+    /// `continue`, which skips to the next iteration of the loop that it is used in.
+    Continue,
+
+    /// `else`, the fallback branch of an `if` statement.
+    Else,
+
+    /// The boolean literal `false`.
+    False,
+
+    /// `for`, which starts an iterating loop.
+    For,
+
+    /// `if`, which starts a conditional statement.
+    If,
+
+    /// `in`, which separates the binding and the iterator of a `for` loop.
+    In,
+
+    /// `priv`, which marks an item as private to its module.
+    Priv,
+
+    /// `pub`, which marks an item as accessible from other modules.
+    Pub,
+
+    /// `return`, which returns a value from a function.
+    Return,
+
+    /// The boolean literal `true`.
+    True,
+
+    /// `while`, which starts a conditional loop.
+    While,
+}
+
+impl Keyword {
+    /// Returns the [`Keyword`] that the provided identifier represents, or `None` if the identifier is
+    /// not reserved.
+    pub fn from_iden(iden: &str) -> Option<Self> {
+        match iden {
+            "as" => Some(Self::As),
+            "break" => Some(Self::Break),
+            "continue" => Some(Self::Continue),
+            "else" => Some(Self::Else),
+            "false" => Some(Self::False),
+            "for" => Some(Self::For),
+            "if" => Some(Self::If),
+            "in" => Some(Self::In),
+            "priv" => Some(Self::Priv),
+            "pub" => Some(Self::Pub),
+            "return" => Some(Self::Return),
+            "true" => Some(Self::True),
+            "while" => Some(Self::While),
+            _ => None,
+        }
+    }
+
+    /// Returns the [`Keyword`] that the provided identifier characters represent, or `None` if the
+    /// identifier is not reserved.  Unlike [`Self::from_iden`], this doesn't require the identifier to
+    /// be collected into a [`String`], which allows the lexer to check every identifier without
+    /// allocating.
+    pub fn from_chars(chars: &[char]) -> Option<Self> {
+        // Every keyword is ASCII and at most 8 characters long (`continue`), so anything else can be
+        // rejected before copying the characters into a buffer.
+        let mut buf = [0u8; 8];
+
+        if chars.len() > buf.len() {
+            return None;
+        }
+
+        for (byte, c) in buf.iter_mut().zip(chars) {
+            if !c.is_ascii() {
+                return None;
+            }
+
+            *byte = *c as u8;
+        }
+
+        Self::from_iden(std::str::from_utf8(&buf[..chars.len()]).ok()?)
+    }
+
+    /// Returns the source text of this [`Keyword`].
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::As => "as",
+            Self::Break => "break",
+            Self::Continue => "continue",
+            Self::Else => "else",
+            Self::False => "false",
+            Self::For => "for",
+            Self::If => "if",
+            Self::In => "in",
+            Self::Priv => "priv",
+            Self::Pub => "pub",
+            Self::Return => "return",
+            Self::True => "true",
+            Self::While => "while",
+        }
+    }
+}
+
+impl fmt::Display for Keyword {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -1,12 +1,27 @@
 //! Provides utilities for tokenizing, or lexing, Flycatcher source.
 
 mod chars;
+mod keyword;
+mod stats;
 mod token;
 
-pub use chars::{is_iden_continue, is_iden_start, is_line_term, is_punctuator, is_white_space};
+pub use chars::{
+    is_iden_continue,
+    is_iden_start,
+    is_line_term,
+    is_punctuator,
+    is_token_start,
+    is_white_space,
+};
+pub use keyword::Keyword;
+pub use stats::{token_stats, TokenStats};
 use std::ops::Range;
 pub use token::{InvalidStrType, Token};
 
+/// The default maximum length, in characters, of an identifier.  This is far longer than any
+/// identifier in normal code, it only exists to protect the compiler from pathological input.
+pub const DEFAULT_MAX_IDEN_LEN: usize = 16 * 1024;
+
 /// A lexer for Flycatcher source.  This lexer leverages the Unicode character set standard, and allows
 /// any valid Unicode text as source.
 pub struct Lexer {
@@ -18,10 +33,24 @@ pub struct Lexer {
     /// slices of tokens.
     source: String,
 
-    /// The range, in the source string, which the current token resides in.  This is used by the lexer
-    /// to keep track of where the next token should be in the source string.  It is also used by the
-    /// [`Self::slice`] method, to efficiently calculate the slice string when needed.
+    /// The byte offset in the source string of each character in `chars`, followed by the length of
+    /// the source string.  Token locations are measured in characters, so this is used by
+    /// [`Self::slice`] to find the bytes of a token without walking the source string.
+    offsets: Vec<usize>,
+
+    /// The range, in characters, which the current token resides in.  This is used by the lexer to keep
+    /// track of where the next token should be in the source string.  It is also used by the
+    /// [`Self::slice`] method, which converts it to byte offsets to calculate the slice string when
+    /// needed.
     loc: Range<usize>,
+
+    /// The maximum length, in characters, of an identifier.  Identifiers which are longer than this
+    /// are emitted as [`Token::Invalid`], rather than being passed on to later stages of the compiler.
+    max_iden_len: usize,
+
+    /// The range of the last token which was not trivia, such as white spaces and comments.  This is
+    /// what diagnostics should usually point at, as the current token may be trivia that was skipped.
+    last_loc: Range<usize>,
 }
 
 impl Lexer {
@@ -29,13 +58,29 @@ impl Lexer {
     /// source string provided, into a [`Vec<char>`].  Additionally also stores the source string, to
     /// allow the lazy calculation of token slices.
     pub fn new(source: String) -> Self {
+        let offsets = source
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .chain(std::iter::once(source.len()))
+            .collect();
+
         Self {
             chars: source.chars().collect(),
             source,
+            offsets,
             loc: 0..0,
+            max_iden_len: DEFAULT_MAX_IDEN_LEN,
+            last_loc: 0..0,
         }
     }
 
+    /// Sets the maximum length, in characters, of an identifier.  Identifiers which exceed this length
+    /// are emitted as a single [`Token::Invalid`] that covers the whole identifier.  Defaults to
+    /// [`DEFAULT_MAX_IDEN_LEN`].
+    pub fn set_max_iden_len(&mut self, len: usize) {
+        self.max_iden_len = len;
+    }
+
     /// Returns the range, in characters, of the current token.  Lexers are initialized with a
     /// [`Range<usize>`] of `0..0`.  This means that if the lexer has not had atleast one iteration,
     /// this function will return the default location.
@@ -43,6 +88,14 @@ impl Lexer {
         self.loc.clone()
     }
 
+    /// Returns the range, in characters, of the last significant token, meaning the last token that
+    /// isn't trivia (see [`Token::is_trivia`]).  Unlike [`Self::loc`], this isn't affected by skipping
+    /// over white spaces and comments, so it is suitable for pointing diagnostics at the last real
+    /// token.  Returns `0..0` if no significant token has been lexed yet.
+    pub fn last_loc(&self) -> Range<usize> {
+        self.last_loc.clone()
+    }
+
     /// Returns the slice of the current token.  This function uses the [`Self::loc`] method to
     /// calculate the location of the current token, and uses that information to get the current span.
     /// If the lexer's token stream (iterator) has ran out of tokens, this function will return an
@@ -54,17 +107,175 @@ impl Lexer {
             return "";
         }
 
-        &self.source[self.loc()]
+        // Locations are measured in characters, but the source string is indexed by bytes, so the
+        // location is converted using the offsets calculated when the lexer was initialized.
+        &self.source[self.offsets[span.start]..self.offsets[span.end]]
     }
-}
 
-impl Iterator for Lexer {
-    type Item = Token;
+    /// Converts this lexer into an iterator which yields each [`Token`] along with its location, rather
+    /// than requiring a call to [`Self::loc`] after every token.
+    pub fn into_spanned(self) -> SpannedLexer {
+        SpannedLexer { lexer: self }
+    }
 
-    /// Calculates the next [`Token`] in the lexer.  This function only returns the *type* of the next
-    /// token.  The value, or *slice*, of the next token can be obtained with the [`Lexer::slice`]
-    /// method.  To get the location of the next token, you can use the [`Lexer::loc`] method.
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Returns the gap between two token locations, and the location that covers both of them, in that
+    /// order.  The locations may be provided in any order.  If the two locations overlap or are
+    /// adjacent, the gap is an empty range at the start of the later location.
+    ///
+    /// This is useful for highlighting only the problematic part of a region in an error message, such
+    /// as the contents between an opening and closing token.
+    pub fn split_span(a: Range<usize>, b: Range<usize>) -> (Range<usize>, Range<usize>) {
+        let (first, second) = if a.start <= b.start { (a, b) } else { (b, a) };
+
+        let gap_start = first.end.min(second.start);
+        let combined_end = first.end.max(second.end);
+
+        (gap_start..second.start, first.start..combined_end)
+    }
+
+    /// Lexes a string literal.  `start_index` is the index of the first character of the token, which
+    /// is the first character of the prefix if there is one, and `quote_index` is the index of the
+    /// opening quote.  The string ends with the same quote character that it was opened with.
+    fn lex_string(&mut self, start_index: usize, quote_index: usize, prefix: Option<String>) -> Token {
+        let quote = self.chars[quote_index];
+
+        // `pos` always starts after the opening quote, so error locations which point at the
+        // character before `pos` can't go before the start of the string.  The error locations still
+        // use saturating arithmetic, so that a future change to this loop can't cause an underflow.
+        let mut pos = quote_index + 1;
+
+        // The first invalid escape found in the string, if any.  Invalid escapes don't end the string,
+        // so we keep lexing until the closing quote and report the escape afterwards.
+        let mut escape_error = None;
+
+        while pos < self.chars.len() {
+            // In this loop, we need to check if the current character is the correct character to
+            // end the string.  We also need to skip over escaped characters.
+
+            let str_char = self.chars[pos];
+
+            if str_char == quote {
+                // The string has ended.
+
+                pos += 1;
+
+                self.loc = start_index..pos;
+
+                if let Some((ty, error_loc)) = escape_error {
+                    return Token::InvalidStr { ty, error_loc };
+                }
+
+                return Token::Str { prefix };
+            } else if is_line_term(str_char) {
+                // If we land here, the string did not end before a new line character was found.
+                // This makes the string invalid.
+
+                self.loc = start_index..pos;
+                return Token::InvalidStr {
+                    ty: InvalidStrType::UnclosedLine,
+                    error_loc: pos.saturating_sub(1)..pos,
+                };
+            } else if str_char == '\\' {
+                // The current character in the string is escaped, but we'll need to see if it is a
+                // Unicode escape, or a normal escaped character.
+
+                pos += 1; // move to the escaped character.
+
+                // Before we do anything, we need to confirm that the string is still valid, and the
+                // next character (the character code) exists.
+                if pos >= self.chars.len() {
+                    // There wasn't a closing quote before the file ended.
+                    self.loc = start_index..pos;
+                    return Token::InvalidStr {
+                        ty: InvalidStrType::UnclosedEOF,
+                        error_loc: pos.saturating_sub(1)..pos,
+                    };
+                } else if is_line_term(self.chars[pos]) {
+                    // The string doesn't end on the line that it starts.
+                    self.loc = start_index..pos;
+                    return Token::InvalidStr {
+                        ty: InvalidStrType::UnclosedLine,
+                        error_loc: pos.saturating_sub(1)..pos,
+                    };
+                }
+
+                let escape_char = self.chars[pos];
+                pos += 1;
+
+                if escape_char == 'x' || escape_char == 'u' {
+                    // This is a Unicode escape, such as `\x{1F600}`.  At this phase in the language,
+                    // we don't have to actually calculate the character, but we do need to verify
+                    // that the escape is well formed.
+                    let escape_start = pos - 2; // the index of the backslash.
+
+                    if pos >= self.chars.len() || self.chars[pos] != '{' {
+                        if escape_error.is_none() {
+                            escape_error = Some((
+                                InvalidStrType::NoOpeningBraceUnicodeEscape,
+                                escape_start..pos,
+                            ));
+                        }
+
+                        continue;
+                    }
+
+                    pos += 1; // skip over the opening brace.
+                    let code_start = pos;
+
+                    while pos < self.chars.len()
+                        && self.chars[pos] != '}'
+                        && self.chars[pos] != quote
+                        && !is_line_term(self.chars[pos]) {
+                        pos += 1;
+                    }
+
+                    if pos >= self.chars.len() || self.chars[pos] != '}' {
+                        // The loop will report the string as unclosed if it ends here, otherwise we
+                        // continue lexing from the character that ended the escape.
+                        if escape_error.is_none() {
+                            escape_error = Some((
+                                InvalidStrType::NoClosingBraceUnicodeEscape,
+                                escape_start..pos,
+                            ));
+                        }
+
+                        continue;
+                    }
+
+                    let code: String = self.chars[code_start..pos].iter().collect();
+                    pos += 1; // skip over the closing brace.
+
+                    // `from_str_radix` accepts a leading `+`, so the digits are checked first.
+                    let valid = !code.is_empty()
+                        && code.len() <= 6
+                        && code.chars().all(|c| c.is_ascii_hexdigit())
+                        && u32::from_str_radix(&code, 16)
+                            .ok()
+                            .and_then(std::char::from_u32)
+                            .is_some();
+
+                    if !valid && escape_error.is_none() {
+                        escape_error = Some((InvalidStrType::InvalidUnicodeEscape, escape_start..pos));
+                    }
+                }
+            } else {
+                // The current character is just a normal string character.
+                pos += 1;
+            }
+        }
+
+        self.loc = start_index..pos;
+
+        // If we get here, the string never ended.
+        Token::InvalidStr {
+            ty: InvalidStrType::UnclosedEOF,
+            error_loc: pos.saturating_sub(1)..pos,
+        }
+    }
+
+    /// Lexes the next [`Token`], and sets [`Self::loc`] to its location.  This is the implementation of
+    /// [`Iterator::next`] for the lexer, which additionally keeps track of the last significant token.
+    fn lex_token(&mut self) -> Option<Token> {
         // This is the starting index of the next token in the lexer.  Because of the way Rust Ranges
         // work, the `end` property is essentially the length of the next token, plus the starting
         // index of it.  This means that the end index is one greater than the actual ending character
@@ -105,7 +316,17 @@ impl Iterator for Lexer {
         } else if is_line_term(start_char) {
             // If the program lands here, the current token is a line terminator.  The process for line
             // termination is exactly the same as the process for white space characters, as seen above.
-            self.loc = start_index..start_index + 1;
+            //
+            // The only exception is a carriage return followed by a line feed (`\r\n`), which is a
+            // single line break on Windows, so it's emitted as a single token.
+            if start_char == '\r'
+                && start_index + 1 < self.chars.len()
+                && self.chars[start_index + 1] == '\n' {
+                self.loc = start_index..start_index + 2;
+            } else {
+                self.loc = start_index..start_index + 1;
+            }
+
             return Some(Token::LineTerm);
         } else if is_punctuator(start_char) {
             // We do punctuators next.  Punctuators are essentially symbols that have some sort of
@@ -114,11 +335,27 @@ impl Iterator for Lexer {
             // The thing is, comments start with the `/` character, which is a punctuator.  Comments
             // start with two slash characters, so we can test if a slash is a comment by checking the
             // next character.
-            
+            //
+            // Similarly, a `#!` at the very start of the file is a shebang line rather than two
+            // punctuators.  Shebangs span until the end of the first line.
+            if start_index == 0
+                && start_char == '#'
+                && self.chars.len() > 1
+                && self.chars[1] == '!' {
+                let mut pos = 2;
+
+                while pos < self.chars.len() && !is_line_term(self.chars[pos]) {
+                    pos += 1;
+                }
+
+                self.loc = 0..pos;
+                return Some(Token::Shebang);
+            }
+
             if start_char == '/' {
                 // We need to check if there is a next character in the `chars` vector, to prevent an
                 // array overflow panic.
-                if start_index >= self.chars.len() {
+                if start_index + 1 >= self.chars.len() {
                     // There is not a character left in the string, so the token must be a punctuator.
 
                     self.loc = start_index..start_index + 1;
@@ -136,12 +373,11 @@ impl Iterator for Lexer {
 
                     // Next, we need to see if the type is a documentation comment or a line comment.
                     // We can do so by checking if the next character is a slash as well.
-                    let next_char = self.chars[pos];
                     let mut ty = Token::LineComment; // This is the type of the token.  We will set
                                                      // this to Token::DocComment if the below if
                                                      // statement is triggered.
-                    
-                    if next_char == '/' {
+
+                    if pos < self.chars.len() && self.chars[pos] == '/' {
                         ty = Token::DocComment;
                         pos += 1; // skip over the third slash.
                     }
@@ -163,75 +399,10 @@ impl Iterator for Lexer {
             self.loc = start_index..start_index + 1;
             return Some(Token::Punctuator);
         } else if start_char == '"' || start_char == '\'' {
-            // Alright, if the program lands here, the current token is a string.  We'll use the
-            // `start_char` to find the end of the string.
-            let mut pos = start_index + 1;
-
-            while pos < self.chars.len() {
-                // In this loop, we need to check if the current character is the correct character to
-                // end the string.  We also need to skip over escaped characters.
-
-                let str_char = self.chars[pos];
-
-                if str_char == start_char {
-                    // The string has ended.
-
-                    pos += 1;
-
-                    self.loc = start_index..pos;
-                    return Some(Token::Str {
-                        prefix: None
-                    });
-                } else if is_line_term(str_char) {
-                    // If we land here, the string did not end before a new line character was found.
-                    // This makes the string invalid.
-
-                    self.loc = start_index..pos;
-                    return Some(Token::InvalidStr {
-                        ty: InvalidStrType::UnclosedLine,
-                        error_loc: pos - 1..pos,
-                    });
-                } else if str_char == '\\' {
-                    // The current character in the string is escaped, but we'll need to see if it is a
-                    // Unicode escape, or a normal escaped character.
-
-                    pos += 1; // move to the escaped character.
-
-                    // Before we do anything, we need to confirm that the string is still valid, and
-                    // the next character (the character code) exists.
-                    if pos >= self.chars.len() {
-                        // There wasn't a closing quote before the file ended.
-                        self.loc = start_index..pos;
-                        return Some(Token::InvalidStr {
-                            ty: InvalidStrType::UnclosedEOF,
-                            error_loc: pos - 1..pos,
-                        });
-                    } else if is_line_term(self.chars[pos]) {
-                        // The string doesn't end on the line that it starts.
-                        self.loc = start_index..pos;
-                        return Some(Token::InvalidStr {
-                            ty: InvalidStrType::UnclosedLine,
-                            error_loc: pos - 1..pos,
-                        });
-                    }
-
-                    // At this phase in the language, we don't have to actually calculate any of the
-                    // character codes, we can simply skip over the next character.  The loop will
-                    // verify that the string is valid.
-                    pos += 1;
-                } else {
-                    // The current character is just a normal string character.
-                    pos += 1;
-                }
-            }
-
-            self.loc = start_index..pos;
-
-            // If we get here, the string never ended.
-            return Some(Token::InvalidStr {
-                ty: InvalidStrType::UnclosedEOF,
-                error_loc: pos - 1..pos,
-            });
+            // Alright, if the program lands here, the current token is a string without a prefix.  The
+            // process of lexing a string is shared with prefixed strings, so it lives in its own
+            // method.
+            return Some(self.lex_string(start_index, start_index, None));
         } else if is_iden_start(start_char) {
             // Alright, the next thing we need to tokenize is identifiers.  Identifiers must start with
             // a Unicode XID character, or an underscore.  An identifier ends when the next character
@@ -239,32 +410,90 @@ impl Iterator for Lexer {
 
             let mut pos = start_index + 1;
 
-            while pos < self.chars.len() {
-                // This checks if the identifier ends at this character or not.
+            while pos < self.chars.len() && is_iden_continue(self.chars[pos]) {
+                pos += 1;
+            }
 
-                let iden_char = self.chars[pos];
+            if pos - start_index > self.max_iden_len {
+                // The identifier is too long to be reasonable, so we report the whole identifier as
+                // an invalid token rather than producing a huge token.
+                self.loc = start_index..pos;
+                return Some(Token::Invalid);
+            }
 
-                if is_iden_continue(iden_char) {
-                    // The current character is an XID continuing character, so we may continue the
-                    // loop.
-                    pos += 1;
-                } else if iden_char == '"' || iden_char == '\'' {
-                    // It looks like the identifier was a string prefix.  String prefixes are simply
-                    // identifiers directly before a string, with no spaces.
-                    //
-                    // This means that we will need to tokenize a string, similar to the process above.
-                }
+            // Keywords are identifiers that are reserved by the language, so we check the name of
+            // the identifier against the list of keywords before anything else.  A keyword is never a
+            // string prefix, so in `return"x"` the string is lexed as its own token.
+            if let Some(keyword) = Keyword::from_chars(&self.chars[start_index..pos]) {
+                self.loc = start_index..pos;
+                return Some(Token::Keyword(keyword));
+            }
+
+            if pos < self.chars.len() && (self.chars[pos] == '"' || self.chars[pos] == '\'') {
+                // It looks like the identifier was a string prefix.  String prefixes are simply
+                // identifiers directly before a string, with no spaces.
+                let prefix = self.chars[start_index..pos].iter().collect();
+                return Some(self.lex_string(start_index, pos, Some(prefix)));
             }
+
+            self.loc = start_index..pos;
+            return Some(Token::Identifier);
         }
 
         // If the program lands here, we can safely assume that no valid token was found.  This means
         // that we can return an `Invalid` token.
         //
-        // We don't know how long the token was intended to be, but the invalid character may only be
-        // up to one character in length.  We will use this as the length to calculate the location of
-        // the invalid token.
-        self.loc = start_index..start_index + 1;
-        return Some(Token::Invalid);
+        // We don't know how long the token was intended to be, so we treat every following character
+        // that can't start a valid token as part of it.  This way, a run of invalid characters is
+        // reported once, rather than once per character.
+        let mut pos = start_index + 1;
+
+        while pos < self.chars.len() && !is_token_start(self.chars[pos]) {
+            pos += 1;
+        }
+
+        self.loc = start_index..pos;
+        Some(Token::Invalid)
+    }
+}
+
+impl Iterator for Lexer {
+    type Item = Token;
+
+    /// Calculates the next [`Token`] in the lexer.  This function only returns the *type* of the next
+    /// token.  The value, or *slice*, of the next token can be obtained with the [`Lexer::slice`]
+    /// method.  To get the location of the next token, you can use the [`Lexer::loc`] method.
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.lex_token()?;
+
+        if !token.is_trivia() {
+            self.last_loc = self.loc.clone();
+        }
+
+        Some(token)
+    }
+}
+
+/// An iterator over the tokens of a [`Lexer`] and their locations, created with
+/// [`Lexer::into_spanned`].
+pub struct SpannedLexer {
+    /// The lexer which tokens are read from.
+    lexer: Lexer,
+}
+
+impl SpannedLexer {
+    /// Returns the underlying [`Lexer`], which may be used to get the slice of the last token.
+    pub fn lexer(&self) -> &Lexer {
+        &self.lexer
+    }
+}
+
+impl Iterator for SpannedLexer {
+    type Item = (Token, Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let token = self.lexer.next()?;
+        Some((token, self.lexer.loc()))
     }
 }
 
@@ -276,7 +505,7 @@ fn test() {
     loop {
         let item = lexer.next();
 
-        if item == None {
+        if item.is_none() {
             // If there is no token left in the lexer, then we must end the loop.
             break;
         }
@@ -286,4 +515,325 @@ fn test() {
         let loc = lexer.loc();
         println!("{:#?}@{}:{} '{}'", item, loc.start, loc.end, lexer.slice());
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn keywords() {
+    let keywords = [
+        Keyword::As,
+        Keyword::Break,
+        Keyword::Continue,
+        Keyword::Else,
+        Keyword::False,
+        Keyword::For,
+        Keyword::If,
+        Keyword::In,
+        Keyword::Priv,
+        Keyword::Pub,
+        Keyword::Return,
+        Keyword::True,
+        Keyword::While,
+    ];
+
+    for keyword in keywords.iter() {
+        let mut lexer = Lexer::new(keyword.as_str().to_string());
+        let token = lexer.next().unwrap();
+
+        assert_eq!(token, Token::Keyword(*keyword));
+        assert_eq!(token.keyword(), Some(*keyword));
+        assert_eq!(lexer.loc(), 0..keyword.as_str().chars().count());
+        assert_eq!(lexer.next(), None);
+    }
+
+    // Identifiers which merely start with a keyword, or are too long or non-ASCII, are not keywords.
+    assert_eq!(Keyword::from_chars(&['w', 'h', 'i', 'l', 'é']), None);
+    assert_eq!(Keyword::from_chars(&['c', 'o', 'n', 't', 'i', 'n', 'u', 'e', 's']), None);
+
+    let mut lexer = Lexer::new("iffy r'raw'".to_string());
+    assert_eq!(lexer.next(), Some(Token::Identifier));
+    assert_eq!(lexer.loc(), 0..4);
+    assert_eq!(lexer.next(), Some(Token::WhiteSpace));
+    assert_eq!(
+        lexer.next(),
+        Some(Token::Str {
+            prefix: Some("r".to_string())
+        })
+    );
+    assert_eq!(lexer.loc(), 5..11);
+    assert_eq!(lexer.next(), None);
+
+    // Keywords are never string prefixes, even when directly followed by a quote.
+    let mut lexer = Lexer::new("return\"x\"".to_string());
+    assert_eq!(lexer.next(), Some(Token::Keyword(Keyword::Return)));
+    assert_eq!(lexer.loc(), 0..6);
+    assert_eq!(lexer.next(), Some(Token::Str { prefix: None }));
+    assert_eq!(lexer.loc(), 6..9);
+    assert_eq!(lexer.next(), None);
+}
+
+#[test]
+fn unicode_escapes() {
+    let mut lexer = Lexer::new("'\\x{1F600}'".to_string());
+    assert_eq!(lexer.next(), Some(Token::Str { prefix: None }));
+    assert_eq!(lexer.loc(), 0..11);
+
+    // Invalid escapes don't end the string, the token still spans up to the closing quote.
+    let mut lexer = Lexer::new("'\\x41' a".to_string());
+    assert_eq!(
+        lexer.next(),
+        Some(Token::InvalidStr {
+            ty: InvalidStrType::NoOpeningBraceUnicodeEscape,
+            error_loc: 1..3,
+        })
+    );
+    assert_eq!(lexer.loc(), 0..6);
+    assert_eq!(lexer.next(), Some(Token::WhiteSpace));
+
+    let mut lexer = Lexer::new("'\\u{41'".to_string());
+    assert_eq!(
+        lexer.next(),
+        Some(Token::InvalidStr {
+            ty: InvalidStrType::NoClosingBraceUnicodeEscape,
+            error_loc: 1..6,
+        })
+    );
+
+    let mut lexer = Lexer::new("'\\x{+41}'".to_string());
+    assert_eq!(
+        lexer.next(),
+        Some(Token::InvalidStr {
+            ty: InvalidStrType::InvalidUnicodeEscape,
+            error_loc: 1..8,
+        })
+    );
+
+    let mut lexer = Lexer::new("'\\x{D800}'".to_string());
+    assert_eq!(
+        lexer.next(),
+        Some(Token::InvalidStr {
+            ty: InvalidStrType::InvalidUnicodeEscape,
+            error_loc: 1..9,
+        })
+    );
+}
+
+#[test]
+fn invalid_runs() {
+    let mut lexer = Lexer::new("\\`\\` a".to_string());
+    assert_eq!(lexer.next(), Some(Token::Invalid));
+    assert_eq!(lexer.loc(), 0..4);
+    assert_eq!(lexer.next(), Some(Token::WhiteSpace));
+    assert_eq!(lexer.next(), Some(Token::Identifier));
+    assert_eq!(lexer.next(), None);
+}
+
+#[test]
+fn shebang() {
+    let mut lexer = Lexer::new("#!/usr/bin/env flycatcher\nfoo #!".to_string());
+    assert_eq!(lexer.next(), Some(Token::Shebang));
+    assert_eq!(lexer.loc(), 0..25);
+    assert_eq!(lexer.next(), Some(Token::LineTerm));
+    assert_eq!(lexer.next(), Some(Token::Identifier));
+    assert_eq!(lexer.next(), Some(Token::WhiteSpace));
+
+    // A shebang may only appear at the start of a file.
+    assert_eq!(lexer.next(), Some(Token::Punctuator));
+    assert_eq!(lexer.next(), Some(Token::Punctuator));
+    assert_eq!(lexer.next(), None);
+
+    let mut lexer = Lexer::new("#foo".to_string());
+    assert_eq!(lexer.next(), Some(Token::Punctuator));
+    assert_eq!(lexer.next(), Some(Token::Identifier));
+    assert_eq!(lexer.loc(), 1..4);
+}
+
+#[test]
+fn split_span() {
+    assert_eq!(Lexer::split_span(2..4, 9..12), (4..9, 2..12));
+    assert_eq!(Lexer::split_span(9..12, 2..4), (4..9, 2..12));
+
+    // Adjacent and overlapping locations have no gap between them.
+    assert_eq!(Lexer::split_span(0..3, 3..5), (3..3, 0..5));
+    assert_eq!(Lexer::split_span(0..6, 2..4), (2..2, 0..6));
+}
+
+#[test]
+fn spanned() {
+    let tokens: Vec<_> = Lexer::new("if x".to_string()).into_spanned().collect();
+
+    assert_eq!(
+        tokens,
+        vec![
+            (Token::Keyword(Keyword::If), 0..2),
+            (Token::WhiteSpace, 2..3),
+            (Token::Identifier, 3..4),
+        ]
+    );
+
+    // Locations are measured in characters, so non-ASCII source must still slice correctly.
+    let mut spanned = Lexer::new("é x é'ü'".to_string()).into_spanned();
+    let mut next = || {
+        let (token, loc) = spanned.next().unwrap();
+        (token, loc, spanned.lexer().slice().to_string())
+    };
+
+    assert_eq!(next(), (Token::Identifier, 0..1, "é".to_string()));
+    assert_eq!(next(), (Token::WhiteSpace, 1..2, " ".to_string()));
+    assert_eq!(next(), (Token::Identifier, 2..3, "x".to_string()));
+    assert_eq!(next(), (Token::WhiteSpace, 3..4, " ".to_string()));
+    assert_eq!(
+        next(),
+        (
+            Token::Str {
+                prefix: Some("é".to_string())
+            },
+            4..8,
+            "é'ü'".to_string()
+        )
+    );
+}
+
+#[test]
+fn span_edge_cases() {
+    // A backslash directly after the opening quote, at the end of the file or line.
+    let mut lexer = Lexer::new("'\\".to_string());
+    assert_eq!(
+        lexer.next(),
+        Some(Token::InvalidStr {
+            ty: InvalidStrType::UnclosedEOF,
+            error_loc: 1..2,
+        })
+    );
+    assert_eq!(lexer.loc(), 0..2);
+    assert_eq!(lexer.next(), None);
+
+    let mut lexer = Lexer::new("\"\\\n".to_string());
+    assert_eq!(
+        lexer.next(),
+        Some(Token::InvalidStr {
+            ty: InvalidStrType::UnclosedLine,
+            error_loc: 1..2,
+        })
+    );
+    assert_eq!(lexer.next(), Some(Token::LineTerm));
+
+    // A lone quote at the end of the file.
+    let mut lexer = Lexer::new("'".to_string());
+    assert_eq!(
+        lexer.next(),
+        Some(Token::InvalidStr {
+            ty: InvalidStrType::UnclosedEOF,
+            error_loc: 0..1,
+        })
+    );
+
+    // An unfinished Unicode escape at the end of the file.
+    let mut lexer = Lexer::new("'\\x".to_string());
+    assert_eq!(
+        lexer.next(),
+        Some(Token::InvalidStr {
+            ty: InvalidStrType::UnclosedEOF,
+            error_loc: 2..3,
+        })
+    );
+
+    // Slashes at the end of the file.
+    let mut lexer = Lexer::new("/".to_string());
+    assert_eq!(lexer.next(), Some(Token::Punctuator));
+    assert_eq!(lexer.next(), None);
+
+    let mut lexer = Lexer::new("//".to_string());
+    assert_eq!(lexer.next(), Some(Token::LineComment));
+    assert_eq!(lexer.loc(), 0..2);
+    assert_eq!(lexer.next(), None);
+}
+
+#[test]
+fn max_iden_len() {
+    let mut lexer = Lexer::new("abcd abcde".to_string());
+    lexer.set_max_iden_len(4);
+
+    assert_eq!(lexer.next(), Some(Token::Identifier));
+    assert_eq!(lexer.next(), Some(Token::WhiteSpace));
+    assert_eq!(lexer.next(), Some(Token::Invalid));
+    assert_eq!(lexer.loc(), 5..10);
+    assert_eq!(lexer.next(), None);
+}
+
+#[test]
+fn last_loc() {
+    let mut lexer = Lexer::new("a; // comment\n  b".to_string());
+
+    assert_eq!(lexer.next(), Some(Token::Identifier));
+    assert_eq!(lexer.next(), Some(Token::Punctuator));
+
+    // Skip over the trivia after the semicolon.
+    for _ in 0..5 {
+        assert!(lexer.next().unwrap().is_trivia());
+    }
+
+    assert_eq!(lexer.loc(), 15..16);
+    assert_eq!(lexer.last_loc(), 1..2);
+
+    assert_eq!(lexer.next(), Some(Token::Identifier));
+    assert_eq!(lexer.last_loc(), 16..17);
+}
+
+#[test]
+fn unclosed_string_recovery() {
+    // An unclosed string only consumes the rest of its own line, so the following lines are still
+    // tokenized normally.
+    let tokens: Vec<_> = Lexer::new("'abc\nx\n`".to_string()).into_spanned().collect();
+
+    assert_eq!(
+        tokens,
+        vec![
+            (
+                Token::InvalidStr {
+                    ty: InvalidStrType::UnclosedLine,
+                    error_loc: 3..4,
+                },
+                0..4
+            ),
+            (Token::LineTerm, 4..5),
+            (Token::Identifier, 5..6),
+            (Token::LineTerm, 6..7),
+            (Token::Invalid, 7..8),
+        ]
+    );
+}
+
+#[test]
+fn crlf() {
+    let source = "// comment\nif x\n\n'unclosed\ny";
+    let lf: Vec<_> = Lexer::new(source.to_string()).collect();
+    let crlf: Vec<_> = Lexer::new(source.replace('\n', "\r\n")).collect();
+
+    // Both line endings produce the same amount of line breaks, and the same types of tokens.  The
+    // locations inside of tokens differ, as each line break is one character longer.
+    let kinds = |tokens: &[Token]| tokens.iter().map(std::mem::discriminant).collect::<Vec<_>>();
+    assert_eq!(kinds(&lf), kinds(&crlf));
+    assert_eq!(crlf.iter().filter(|token| token.is_line_term()).count(), 4);
+
+    let mut lexer = Lexer::new("\r\n\r\r".to_string());
+    assert_eq!(lexer.next(), Some(Token::LineTerm));
+    assert_eq!(lexer.loc(), 0..2);
+    assert_eq!(lexer.next(), Some(Token::LineTerm));
+    assert_eq!(lexer.loc(), 2..3);
+    assert_eq!(lexer.next(), Some(Token::LineTerm));
+    assert_eq!(lexer.next(), None);
+}
+
+#[test]
+fn slice_long_source() {
+    // Slicing every token of a long non-ASCII source should rebuild the source exactly.
+    let source = "héllo wörld; 'ü' // ñ\n".repeat(20_000);
+    let mut lexer = Lexer::new(source.clone());
+    let mut rebuilt = String::with_capacity(source.len());
+
+    while lexer.next().is_some() {
+        rebuilt.push_str(lexer.slice());
+    }
+
+    assert_eq!(rebuilt, source);
+}
@@ -0,0 +1,92 @@
+use super::{Lexer, Token};
+
+/// The amount of each type of token in a piece of Flycatcher source, calculated by [`token_stats`].
+/// Every token is counted, including white spaces and comments.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TokenStats {
+    /// The total amount of tokens in the source.
+    pub total: usize,
+
+    /// The amount of [`Token::Invalid`] tokens.
+    pub invalid: usize,
+
+    /// The amount of [`Token::WhiteSpace`] tokens.
+    pub white_spaces: usize,
+
+    /// The amount of [`Token::LineTerm`] tokens.
+    pub line_terms: usize,
+
+    /// The amount of [`Token::LineComment`] tokens.
+    pub line_comments: usize,
+
+    /// The amount of [`Token::DocComment`] tokens.
+    pub doc_comments: usize,
+
+    /// The amount of [`Token::Shebang`] tokens.
+    pub shebangs: usize,
+
+    /// The amount of [`Token::Punctuator`] tokens.
+    pub punctuators: usize,
+
+    /// The amount of [`Token::Identifier`] tokens.
+    pub identifiers: usize,
+
+    /// The amount of [`Token::Keyword`] tokens.
+    pub keywords: usize,
+
+    /// The amount of [`Token::Str`] tokens.
+    pub strings: usize,
+
+    /// The amount of [`Token::InvalidStr`] tokens.
+    pub invalid_strings: usize,
+}
+
+/// Counts the tokens in the provided source, by their type.
+pub fn token_stats(source: &str) -> TokenStats {
+    let mut stats = TokenStats::default();
+
+    for token in Lexer::new(source.to_string()) {
+        stats.total += 1;
+
+        let count = match token {
+            Token::Invalid => &mut stats.invalid,
+            Token::WhiteSpace => &mut stats.white_spaces,
+            Token::LineTerm => &mut stats.line_terms,
+            Token::LineComment => &mut stats.line_comments,
+            Token::DocComment => &mut stats.doc_comments,
+            Token::Shebang => &mut stats.shebangs,
+            Token::Punctuator => &mut stats.punctuators,
+            Token::Identifier => &mut stats.identifiers,
+            Token::Keyword(_) => &mut stats.keywords,
+            Token::Str { .. } => &mut stats.strings,
+            Token::InvalidStr { .. } => &mut stats.invalid_strings,
+        };
+
+        *count += 1;
+    }
+
+    stats
+}
+
+#[test]
+fn test() {
+    let stats = token_stats("/// Docs.\nif x { println('hi'); } // done\n`");
+
+    assert_eq!(
+        stats,
+        TokenStats {
+            total: 19,
+            invalid: 1,
+            white_spaces: 5,
+            line_terms: 2,
+            line_comments: 1,
+            doc_comments: 1,
+            shebangs: 0,
+            punctuators: 5,
+            identifiers: 2,
+            keywords: 1,
+            strings: 1,
+            invalid_strings: 0,
+        }
+    );
+}
@@ -1,3 +1,5 @@
+use super::Keyword;
+use std::fmt;
 use std::ops::Range;
 
 /// The reason why a string is invalid.
@@ -11,15 +13,27 @@ pub enum InvalidStrType {
 
     /// There was no opening brace in a Unicode character code escape: `\x{0000}`.
     NoOpeningBraceUnicodeEscape,
+
+    /// There was no closing brace in a Unicode character code escape: `\x{0000}`.
+    NoClosingBraceUnicodeEscape,
+
+    /// The character code of a Unicode escape was empty, was not hexadecimal, was longer than six
+    /// digits or was not a valid Unicode scalar value.
+    InvalidUnicodeEscape,
 }
 
 /// Types of tokens that may be emitted by the Flycatcher lexer.  At this phase, tokens consist of
-/// white spaces, line breaks, comments, punctuators and literals.  Keywords do not exist in this
-/// phase, they are just identifiers.
+/// white spaces, line breaks, comments, punctuators, identifiers, keywords and literals.  Keywords are
+/// identifiers which are reserved by the language, see [`Keyword`].
 #[derive(Clone, Debug, PartialEq)]
 pub enum Token {
     /// An invalid token, which matches when no other token matches.  It is essentially a low-priority
-    /// catchall that catches invalid characters.
+    /// catchall that catches invalid characters.  A run of consecutive invalid characters is emitted as
+    /// a single invalid token.
+    ///
+    /// This token is also emitted for an identifier which is longer than the lexer's maximum
+    /// identifier length (see [`Lexer::set_max_iden_len`](super::Lexer::set_max_iden_len)), in which
+    /// case it covers the whole identifier and doesn't contain any invalid characters.
     Invalid,
 
     /// A Unicode white space character.  Flycatcher does not include *all* Unicode white space
@@ -37,9 +51,20 @@ pub enum Token {
     /// but they may be used for the automatic generation of documentation for an item.
     DocComment,
 
+    /// A shebang line at the very start of a file, such as `#!/usr/bin/env flycatcher`.  Shebangs have
+    /// no functional value, they allow Flycatcher scripts to be executed directly on Unix-like systems.
+    Shebang,
+
     /// A punctuator, such as a mathematic operator.
     Punctuator,
 
+    /// An identifier, such as the name of a variable.  Identifiers start with a Unicode XID starting
+    /// character or an underscore, followed by any amount of Unicode XID continuing characters.
+    Identifier,
+
+    /// An identifier which is reserved by the Flycatcher language, such as `if` or `while`.
+    Keyword(Keyword),
+
     /// A string literal.  Strings may start and end with `"` or `'`.
     Str {
         /// The prefix before the string literal, such as:
@@ -50,12 +75,19 @@ pub enum Token {
         prefix: Option<String>,
     },
 
-    /// An string literal which either never ends, or does not end on the same line.
+    /// A string literal which either never ends, does not end on the same line, or contains an invalid
+    /// Unicode escape (see [`InvalidStrType::NoOpeningBraceUnicodeEscape`],
+    /// [`InvalidStrType::NoClosingBraceUnicodeEscape`] and [`InvalidStrType::InvalidUnicodeEscape`]).
+    ///
+    /// An invalid escape doesn't end the string, so the location of the token still reaches the
+    /// closing quote.  If a string has several invalid escapes, only the first one is reported.
     InvalidStr {
         /// The reason why the string is invalid.
         ty: InvalidStrType,
 
-        /// The location where the error occurred.
+        /// The location where the error occurred.  For unclosed strings, this is the character before
+        /// the end of the line or file.  For invalid escapes, this is the escape itself, from the
+        /// backslash to the end of the escape.
         error_loc: Range<usize>,
     },
 }
@@ -64,36 +96,69 @@ impl Token {
     /// Returns `true` if this [`Token`] is a white space character.
     #[inline]
     pub fn is_white_space(&self) -> bool {
-        match self {
-            Self::WhiteSpace => true,
-            _ => false,
-        }
+        matches!(self, Self::WhiteSpace)
     }
 
     /// Returns `true` if this [`Token`] is a line terminating white space character.
     #[inline]
     pub fn is_line_term(&self) -> bool {
-        match self {
-            Self::LineTerm => true,
-            _ => false,
-        }
+        matches!(self, Self::LineTerm)
     }
 
     /// Returns `true` if this [`Token`] is either a line comment or a documentation comment.
     #[inline]
     pub fn is_comment(&self) -> bool {
-        match self {
-            Self::LineComment => true,
-            Self::DocComment => true,
-            _ => false,
-        }
+        matches!(self, Self::LineComment | Self::DocComment)
+    }
+
+    /// Returns `true` if this [`Token`] has no semantic meaning, meaning it is a white space, a line
+    /// terminator, a comment or a shebang.
+    #[inline]
+    pub fn is_trivia(&self) -> bool {
+        matches!(
+            self,
+            Self::WhiteSpace | Self::LineTerm | Self::LineComment | Self::DocComment | Self::Shebang
+        )
     }
 
     /// Returns `true` if this [`Token`] is a punctuator.
     pub fn is_punctuator(&self) -> bool {
+        matches!(self, Self::Punctuator)
+    }
+
+    /// Returns the [`Keyword`] that this [`Token`] represents, or `None` if it isn't a keyword.
+    #[inline]
+    pub fn keyword(&self) -> Option<Keyword> {
+        match self {
+            Self::Keyword(keyword) => Some(*keyword),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    /// Formats a user facing name of this [`Token`], such as `identifier` or `keyword 'if'`.  This is
+    /// intended for use in diagnostics, which may need to name any token.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Punctuator => true,
-            _ => false
+            Self::Invalid => write!(f, "invalid token"),
+            Self::WhiteSpace => write!(f, "white space"),
+            Self::LineTerm => write!(f, "line break"),
+            Self::LineComment => write!(f, "comment"),
+            Self::DocComment => write!(f, "documentation comment"),
+            Self::Shebang => write!(f, "shebang"),
+            Self::Punctuator => write!(f, "punctuator"),
+            Self::Identifier => write!(f, "identifier"),
+            Self::Keyword(keyword) => write!(f, "keyword '{}'", keyword),
+            Self::Str { .. } => write!(f, "string"),
+            Self::InvalidStr { .. } => write!(f, "invalid string"),
         }
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn display() {
+    assert_eq!(Token::Keyword(Keyword::While).to_string(), "keyword 'while'");
+    assert_eq!(Token::Punctuator.to_string(), "punctuator");
+    assert_eq!(Token::Invalid.to_string(), "invalid token");
+}
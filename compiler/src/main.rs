@@ -9,7 +9,7 @@ fn main() {
     loop {
         let item = lexer.next();
 
-        if item == None {
+        if item.is_none() {
             // If there is no token left in the lexer, then we must end the loop.
             break;
         }